@@ -0,0 +1,333 @@
+//! Load-testing harness that drives synthetic traffic through the bridge and
+//! emits structured JSON reports.
+//!
+//! A workload is a JSON file describing the target, the producer fan-out, the
+//! total number of events, a per-event template (reusing the `ProjectEvent` /
+//! `AccountEvent` shapes), and a target rate. Each run writes a timestamped
+//! report into a configurable folder and can be compared against a previous
+//! report to flag regressions.
+//!
+//! Latency is measured on the producer side only — the round trip of each
+//! `PutRecord` call into Kinesis — not the full Kinesis→DynamoDB path, so it is
+//! reported as `put_record_latency_ms` rather than end-to-end latency.
+
+use crate::kinesis::KinesisWrapper;
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use color_eyre::eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// Description of a single load-test workload, loaded from a JSON file.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct Workload {
+    /// Human-readable name, defaults to the file stem if absent.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Target Kinesis stream to `PutRecord` synthetic events to.
+    pub stream_name: String,
+    /// Number of concurrent producer tasks.
+    pub producer_tasks: usize,
+    /// Total number of events to produce across all producers.
+    pub total_events: usize,
+    /// Target aggregate rate, in events per second.
+    pub target_rate: f64,
+    /// Per-event template; each event is this value with a fresh partition key.
+    pub event_template: serde_json::Value,
+}
+
+/// Latency distribution, in milliseconds.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Percentiles {
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+}
+
+/// Commit metadata captured at run time, for correlating reports with code.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitMetadata {
+    pub commit: String,
+    pub dirty: bool,
+}
+
+/// The structured result of a single workload run.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BenchReport {
+    pub run_id: Uuid,
+    pub timestamp_ms: i64,
+    pub git: GitMetadata,
+    pub workload_name: String,
+    /// Events requested by the workload (and, after even distribution across
+    /// producers, the number actually attempted).
+    pub total_events: usize,
+    /// Events that were successfully `PutRecord`ed (`total_events - errors`).
+    pub sent_events: usize,
+    pub errors: u64,
+    /// Achieved throughput, in successfully-sent events per second.
+    pub achieved_throughput: f64,
+    /// `PutRecord` round-trip latency (Kinesis ingest only, not end-to-end).
+    pub put_record_latency_ms: Percentiles,
+}
+
+/// Run every workload file in turn, writing a report for each into
+/// `report_dir`. If `compare_to` is set, each report is checked against the
+/// baseline for the *same workload name* and regressions beyond `threshold` (a
+/// fractional increase) are logged.
+pub async fn run(
+    kinesis_config: aws_sdk_kinesis::Config,
+    workload_paths: &[PathBuf],
+    report_dir: &Path,
+    compare_to: Option<&Path>,
+    threshold: f64,
+) -> Result<()> {
+    std::fs::create_dir_all(report_dir).wrap_err("Failed to create report directory")?;
+
+    let baseline = compare_to.map(load_report).transpose()?;
+
+    for path in workload_paths {
+        let workload = load_workload(path)?;
+        let client = KinesisWrapper::new(workload.stream_name.clone(), kinesis_config.clone());
+        let report = run_workload(&client, &workload).await?;
+
+        if let Some(baseline) = &baseline {
+            // Only compare like against like; a baseline for a different
+            // workload says nothing about this one.
+            if baseline.workload_name == report.workload_name {
+                compare(baseline, &report, threshold);
+            } else {
+                tracing::info!(
+                    "Skipping regression check: baseline is for workload `{}`, not `{}`",
+                    baseline.workload_name,
+                    report.workload_name
+                );
+            }
+        }
+
+        write_report(report_dir, &report)?;
+    }
+
+    Ok(())
+}
+
+/// Drive a single workload and collect its results.
+#[tracing::instrument(skip(client, workload), fields(workload = %workload_name(workload)))]
+async fn run_workload(client: &KinesisWrapper, workload: &Workload) -> Result<BenchReport> {
+    let latencies = Arc::new(Mutex::new(Vec::with_capacity(workload.total_events)));
+    let errors = Arc::new(AtomicU64::new(0));
+
+    // Spread the events and the target rate evenly across producer tasks,
+    // handing the remainder of an uneven split to the first tasks so the total
+    // attempted matches `total_events` exactly rather than silently dropping it.
+    let tasks = workload.producer_tasks.max(1);
+    let per_task = workload.total_events / tasks;
+    let remainder = workload.total_events % tasks;
+    let per_task_interval = if workload.target_rate > 0.0 {
+        Duration::from_secs_f64(tasks as f64 / workload.target_rate)
+    } else {
+        Duration::ZERO
+    };
+
+    let started = Instant::now();
+    let mut handles = Vec::with_capacity(tasks);
+    for task in 0..tasks {
+        let client = client.clone();
+        let template = workload.event_template.clone();
+        let latencies = Arc::clone(&latencies);
+        let errors = Arc::clone(&errors);
+        let task_events = per_task + usize::from(task < remainder);
+
+        handles.push(tokio::task::spawn(async move {
+            for _ in 0..task_events {
+                let (partition_key, data) = synthesize_event(&template);
+
+                let sent = Instant::now();
+                match client.put_record(&partition_key, data).await {
+                    Ok(()) => latencies
+                        .lock()
+                        .await
+                        .push(sent.elapsed().as_secs_f64() * 1000.0),
+                    Err(err) => {
+                        tracing::error!("PutRecord failed: {err:#}");
+                        errors.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+
+                if !per_task_interval.is_zero() {
+                    tokio::time::sleep(per_task_interval).await;
+                }
+            }
+        }));
+    }
+
+    for handle in handles {
+        handle.await.wrap_err("Producer task panicked")?;
+    }
+    let elapsed = started.elapsed().as_secs_f64();
+
+    let mut latencies = Arc::try_unwrap(latencies)
+        .expect("all producers finished")
+        .into_inner();
+    let sent = latencies.len();
+
+    Ok(BenchReport {
+        run_id: Uuid::new_v4(),
+        timestamp_ms: Utc::now().timestamp_millis(),
+        git: git_metadata(),
+        workload_name: workload_name(workload),
+        total_events: workload.total_events,
+        sent_events: sent,
+        errors: errors.load(Ordering::Relaxed),
+        achieved_throughput: if elapsed > 0.0 {
+            sent as f64 / elapsed
+        } else {
+            0.0
+        },
+        put_record_latency_ms: percentiles(&mut latencies),
+    })
+}
+
+/// Build a single event from the template, returning its partition key and the
+/// serialized payload.
+fn synthesize_event(template: &serde_json::Value) -> (String, Vec<u8>) {
+    let mut event = template.clone();
+
+    // Give each event a fresh identity so records fan out across shards. Reuse
+    // whichever key the template shape implies.
+    let partition_key = if let Some(object) = event.as_object_mut() {
+        if object.contains_key("ProjectId") {
+            let id = Uuid::new_v4().to_string();
+            object.insert("ProjectId".to_string(), serde_json::json!(id));
+            id
+        } else if object.contains_key("AccountId") {
+            let id = Uuid::new_v4().as_u128() as u64;
+            object.insert("AccountId".to_string(), serde_json::json!(id));
+            id.to_string()
+        } else {
+            Uuid::new_v4().to_string()
+        }
+    } else {
+        Uuid::new_v4().to_string()
+    };
+
+    let data = serde_json::to_vec(&event).expect("template is serializable");
+    (partition_key, data)
+}
+
+/// Compute p50/p90/p99 over the collected latencies (mutates by sorting).
+fn percentiles(latencies: &mut [f64]) -> Percentiles {
+    if latencies.is_empty() {
+        return Percentiles {
+            p50: 0.0,
+            p90: 0.0,
+            p99: 0.0,
+        };
+    }
+    latencies.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let at = |q: f64| {
+        let idx = ((latencies.len() as f64 - 1.0) * q).round() as usize;
+        latencies[idx]
+    };
+
+    Percentiles {
+        p50: at(0.50),
+        p90: at(0.90),
+        p99: at(0.99),
+    }
+}
+
+fn workload_name(workload: &Workload) -> String {
+    workload
+        .name
+        .clone()
+        .unwrap_or_else(|| workload.stream_name.clone())
+}
+
+fn load_workload(path: &Path) -> Result<Workload> {
+    let file = std::fs::File::open(path)
+        .wrap_err_with(|| format!("Failed to open workload file {}", path.display()))?;
+    let mut workload: Workload =
+        serde_json::from_reader(file).wrap_err("Failed to parse workload file")?;
+    if workload.name.is_none() {
+        workload.name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned());
+    }
+    Ok(workload)
+}
+
+fn load_report(path: &Path) -> Result<BenchReport> {
+    // Reports are serialized with the same field names we deserialize here.
+    let file = std::fs::File::open(path)
+        .wrap_err_with(|| format!("Failed to open report file {}", path.display()))?;
+    serde_json::from_reader(file).wrap_err("Failed to parse report file")
+}
+
+fn write_report(report_dir: &Path, report: &BenchReport) -> Result<()> {
+    let file_name = format!("{}-{}.json", report.timestamp_ms, report.run_id);
+    let path = report_dir.join(file_name);
+    let file = std::fs::File::create(&path)
+        .wrap_err_with(|| format!("Failed to create report file {}", path.display()))?;
+    serde_json::to_writer_pretty(file, report).wrap_err("Failed to write report")?;
+    tracing::info!("Wrote benchmark report to {}", path.display());
+    Ok(())
+}
+
+/// Flag regressions where the new run is more than `threshold` (fractional)
+/// worse than the baseline on throughput or p99 latency.
+fn compare(baseline: &BenchReport, current: &BenchReport, threshold: f64) {
+    if baseline.achieved_throughput > 0.0 {
+        let drop = (baseline.achieved_throughput - current.achieved_throughput)
+            / baseline.achieved_throughput;
+        if drop > threshold {
+            tracing::warn!(
+                "Throughput regression: {:.1}/s -> {:.1}/s ({:.0}% drop)",
+                baseline.achieved_throughput,
+                current.achieved_throughput,
+                drop * 100.0
+            );
+        }
+    }
+    if baseline.put_record_latency_ms.p99 > 0.0 {
+        let rise = (current.put_record_latency_ms.p99 - baseline.put_record_latency_ms.p99)
+            / baseline.put_record_latency_ms.p99;
+        if rise > threshold {
+            tracing::warn!(
+                "p99 PutRecord latency regression: {:.1}ms -> {:.1}ms ({:.0}% rise)",
+                baseline.put_record_latency_ms.p99,
+                current.put_record_latency_ms.p99,
+                rise * 100.0
+            );
+        }
+    }
+}
+
+/// Best-effort capture of the current commit and working-tree state.
+fn git_metadata() -> GitMetadata {
+    let commit = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .map(|out| String::from_utf8_lossy(&out.stdout).trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let dirty = Command::new("git")
+        .args(["status", "--porcelain"])
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .map(|out| !out.stdout.is_empty())
+        .unwrap_or(false);
+
+    GitMetadata { commit, dirty }
+}
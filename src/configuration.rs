@@ -9,6 +9,62 @@ pub struct BridgeServiceConfig {
     /// Override for the AWS endpoint provider, used to point the service at LocalStack
     pub override_aws_endpoint: Option<String>,
     pub dynamo_table_name: String,
+    /// How long each shard reader sleeps between `GetRecords` polls, in
+    /// milliseconds. Defaults to 200 ms to stay under the per-shard limit.
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+    /// DynamoDB table used to persist per-shard checkpoints so consumers resume
+    /// instead of replaying each shard from `TrimHorizon`.
+    pub checkpoint_table_name: String,
+    /// Flush a shard's checkpoint back to DynamoDB once this many milliseconds
+    /// have passed since the last flush, even if the record threshold has not
+    /// been reached. The interval is checked as each record is handled, so a
+    /// shard that receives no further records keeps its pending checkpoint
+    /// until the shard closes.
+    #[serde(default = "default_checkpoint_flush_interval_ms")]
+    pub checkpoint_flush_interval_ms: u64,
+    /// Flush a shard's checkpoint after this many records have been handled.
+    #[serde(default = "default_checkpoint_flush_records")]
+    pub checkpoint_flush_records: u64,
+    /// How many times to attempt a `BatchWriteItem` (retrying the returned
+    /// `UnprocessedItems`) before surfacing the remainder as an error.
+    #[serde(default = "default_max_write_attempts")]
+    pub max_write_attempts: u32,
+    /// What to do when a Kinesis record fails to deserialize into an `Event`.
+    #[serde(default)]
+    pub on_deserialize_error: OnDeserializeError,
+    /// DynamoDB table used to capture records that fail to deserialize when
+    /// `on_deserialize_error` is `deadletter`.
+    pub dead_letter_table_name: Option<String>,
+}
+
+/// Policy for records that fail `Event::try_from` in a shard reader.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnDeserializeError {
+    /// Log the error and drop the record (the historical behavior).
+    #[default]
+    Skip,
+    /// Persist the raw record to the dead-letter table for later inspection.
+    DeadLetter,
+    /// Propagate the error, aborting the shard reader.
+    Fail,
+}
+
+fn default_poll_interval_ms() -> u64 {
+    200
+}
+
+fn default_checkpoint_flush_interval_ms() -> u64 {
+    5_000
+}
+
+fn default_checkpoint_flush_records() -> u64 {
+    100
+}
+
+fn default_max_write_attempts() -> u32 {
+    8
 }
 
 impl BridgeServiceConfig {
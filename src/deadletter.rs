@@ -0,0 +1,139 @@
+//! Capture records that fail to deserialize so they can be inspected and
+//! replayed later, instead of being logged and dropped.
+
+use crate::dynamo::{attribute_to_json, json_object_to_attributes};
+use crate::event::Event;
+
+use aws_sdk_dynamodb::model::AttributeValue;
+use aws_sdk_dynamodb::types::Blob;
+use aws_sdk_dynamodb::{Client, Config};
+use aws_sdk_kinesis::types::Blob as KinesisBlob;
+use chrono::Utc;
+use color_eyre::eyre::{Result, WrapErr};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// A single record that could not be parsed into an [`Event`].
+pub struct DeadLetter<'a> {
+    pub shard_id: &'a str,
+    pub sequence_number: &'a str,
+    pub partition_key: &'a str,
+    pub raw: &'a [u8],
+    pub error: String,
+}
+
+/// Stores dead-lettered records in a DynamoDB table, keyed by `ShardId` +
+/// `SequenceNumber`.
+#[derive(Clone)]
+pub struct DeadLetterStore {
+    table_name: String,
+    client: Client,
+}
+
+impl DeadLetterStore {
+    pub fn new(table_name: String, config: Config) -> Self {
+        Self {
+            table_name,
+            client: Client::from_conf(config),
+        }
+    }
+
+    /// Persist a dead-lettered record. The raw bytes are stored verbatim as
+    /// binary so they can be replayed exactly as received.
+    #[tracing::instrument(skip(self, letter), fields(shard_id = letter.shard_id, sequence_number = letter.sequence_number))]
+    pub async fn write(&self, letter: &DeadLetter<'_>) -> Result<()> {
+        // Build the scalar fields through the shared JSON→AttributeValue path,
+        // then attach the raw payload as a binary attribute.
+        let mut item = json_object_to_attributes(
+            serde_json::json!({
+                "ShardId": letter.shard_id,
+                "SequenceNumber": letter.sequence_number,
+                "PartitionKey": letter.partition_key,
+                "Error": letter.error,
+                "DeadLetteredAt": Utc::now().timestamp_millis(),
+            })
+            .as_object()
+            .cloned()
+            .expect("object literal"),
+        );
+        item.insert(
+            "RawMessage".to_string(),
+            AttributeValue::B(Blob::new(letter.raw.to_vec())),
+        );
+
+        self.client
+            .put_item()
+            .table_name(&self.table_name)
+            .set_item(Some(item))
+            .send()
+            .await
+            .wrap_err("Failed to write dead-letter record")?;
+
+        Ok(())
+    }
+
+    /// Read dead-lettered records back, re-attempt `Event::try_from`, and
+    /// forward successfully parsed events into the normal channel. Returns the
+    /// number of records successfully replayed.
+    #[tracing::instrument(skip(self, event_tx))]
+    pub async fn replay(&self, event_tx: &UnboundedSender<Event>) -> Result<usize> {
+        let mut replayed = 0;
+        let mut exclusive_start_key = None;
+
+        loop {
+            let resp = self
+                .client
+                .scan()
+                .table_name(&self.table_name)
+                .set_exclusive_start_key(exclusive_start_key.clone())
+                .send()
+                .await
+                .wrap_err("Failed to scan dead-letter table")?;
+
+            for item in resp.items().unwrap_or_default() {
+                let Some(raw) = item.get("RawMessage").and_then(|v| v.as_b().ok()) else {
+                    continue;
+                };
+                let blob = KinesisBlob::new(raw.as_ref().to_vec());
+                match Event::try_from(&blob) {
+                    Ok(event) => {
+                        event_tx
+                            .send(event)
+                            .wrap_err("Failed to forward replayed event")?;
+                        // Delete the item once it has been forwarded so a second
+                        // `replay` doesn't re-emit it (idempotency).
+                        self.delete(item).await?;
+                        replayed += 1;
+                    }
+                    Err(err) => {
+                        // Still malformed; leave it in the table and surface the
+                        // stored item as JSON for inspection.
+                        let as_json = attribute_to_json(AttributeValue::M(item.clone()));
+                        tracing::warn!(record = %as_json, "Dead-letter record still fails to parse: {err}");
+                    }
+                }
+            }
+
+            exclusive_start_key = resp.last_evaluated_key().cloned();
+            if exclusive_start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(replayed)
+    }
+
+    /// Delete a dead-letter item, using its `ShardId` + `SequenceNumber` key.
+    async fn delete(&self, item: &std::collections::HashMap<String, AttributeValue>) -> Result<()> {
+        let mut request = self.client.delete_item().table_name(&self.table_name);
+        for key in ["ShardId", "SequenceNumber"] {
+            if let Some(value) = item.get(key) {
+                request = request.key(key, value.clone());
+            }
+        }
+        request
+            .send()
+            .await
+            .wrap_err("Failed to delete dead-letter record")?;
+        Ok(())
+    }
+}
@@ -1,20 +1,36 @@
+use crate::event::Event;
+use crate::sink::EventSink;
+
+use async_trait::async_trait;
 use aws_sdk_dynamodb::model::{AttributeValue, PutRequest, WriteRequest};
 use aws_sdk_dynamodb::{Client, Config};
 use chrono::Utc;
 use color_eyre::eyre::{eyre, Result, WrapErr};
+use rand::Rng;
 use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::time::Duration;
 use uuid::Uuid;
 
+/// Base delay for the full-jitter backoff used when retrying throttled writes.
+const BACKOFF_BASE: Duration = Duration::from_millis(50);
+/// Upper bound on a single backoff sleep.
+const BACKOFF_CAP: Duration = Duration::from_secs(20);
+
 pub struct DynamoWrapper {
     table_name: String,
     client: Client,
+    /// How many times to attempt a batch (including retries of
+    /// `UnprocessedItems`) before surfacing the remainder as an error.
+    max_write_attempts: u32,
 }
 
 impl DynamoWrapper {
-    pub fn new(table_name: String, config: Config) -> Self {
+    pub fn new(table_name: String, config: Config, max_write_attempts: u32) -> Self {
         Self {
             table_name,
             client: Client::from_conf(config),
+            max_write_attempts,
         }
     }
 
@@ -74,16 +90,158 @@ impl DynamoWrapper {
 
         Ok(())
     }
+
+    /// Write a batch of requests, retrying any `UnprocessedItems` the service
+    /// returns under throttling with full-jitter exponential backoff. Gives up
+    /// after `max_write_attempts`, returning an error that reports how many
+    /// requests are still unprocessed.
+    #[tracing::instrument(skip(self, requests))]
+    async fn write_requests(&self, requests: Vec<WriteRequest>) -> Result<()> {
+        let mut pending = requests;
+
+        for attempt in 0..self.max_write_attempts {
+            if pending.is_empty() {
+                return Ok(());
+            }
+
+            // Every attempt after the first waits a full-jitter backoff:
+            // sleep = random(0, min(cap, base * 2^attempt)).
+            if attempt > 0 {
+                tokio::time::sleep(backoff(attempt)).await;
+            }
+
+            let resp = self
+                .client
+                .batch_write_item()
+                .request_items(&self.table_name, pending)
+                .send()
+                .await
+                .wrap_err("Failed to batch write")?;
+
+            pending = resp
+                .unprocessed_items()
+                .and_then(|items| items.get(&self.table_name))
+                .cloned()
+                .unwrap_or_default();
+        }
+
+        if pending.is_empty() {
+            Ok(())
+        } else {
+            Err(eyre!(
+                "Gave up after {} attempts with {} unprocessed item(s)",
+                self.max_write_attempts,
+                pending.len()
+            ))
+        }
+    }
+}
+
+#[async_trait]
+impl EventSink for DynamoWrapper {
+    async fn write(&self, events: &[Event]) -> Result<()> {
+        let requests = events.iter().map(write_request_from_event).collect();
+        self.write_requests(requests).await
+    }
+}
+
+/// Full-jitter backoff: `random(0, min(cap, base * 2^attempt))`.
+fn backoff(attempt: u32) -> Duration {
+    let exponential = BACKOFF_BASE
+        .as_millis()
+        .saturating_mul(1u128 << attempt.min(31));
+    let ceiling = exponential.min(BACKOFF_CAP.as_millis()) as u64;
+    Duration::from_millis(rand::thread_rng().gen_range(0..=ceiling))
+}
+
+/// Build a DynamoDB `WriteRequest` for an event, keyed by `ProjectId` or
+/// `AccountId` depending on the variant.
+fn write_request_from_event(event: &Event) -> WriteRequest {
+    let mut item = match event {
+        Event::Project(project_event) => {
+            let mut item = json_object_to_attributes(project_event.event_data.clone());
+            item.insert(
+                "ProjectId".to_string(),
+                AttributeValue::S(project_event.project_id.to_string()),
+            );
+            item.insert(
+                "EntityType".to_string(),
+                AttributeValue::S(project_event.entity_type.clone()),
+            );
+            item.insert(
+                "EntityId".to_string(),
+                AttributeValue::S(project_event.entity_id.clone()),
+            );
+            item
+        }
+        Event::Account(account_event) => {
+            let mut item = json_object_to_attributes(account_event.event_data.clone());
+            item.insert(
+                "AccountId".to_string(),
+                AttributeValue::N(account_event.account_id.to_string()),
+            );
+            item
+        }
+    };
+    item.insert(
+        "EventTimestamp".to_string(),
+        AttributeValue::N(Utc::now().timestamp_millis().to_string()),
+    );
+
+    let put_request = PutRequest::builder().set_item(Some(item)).build();
+    WriteRequest::builder().put_request(put_request).build()
 }
 
 fn json_to_attribute(value: Value) -> AttributeValue {
     match value {
+        Value::Null => AttributeValue::Null(true),
+        Value::Bool(b) => AttributeValue::Bool(b),
         Value::Number(num) => AttributeValue::N(num.to_string()),
         Value::String(string) => AttributeValue::S(string),
-        Value::Null => todo!(),
-        Value::Bool(_) => todo!(),
-        Value::Array(_) => todo!(),
-        Value::Object(_) => todo!(),
+        Value::Array(vec) => AttributeValue::L(vec.into_iter().map(json_to_attribute).collect()),
+        Value::Object(map) => AttributeValue::M(json_object_to_attributes(map)),
+    }
+}
+
+/// Convert a JSON object into the `HashMap<String, AttributeValue>` shape that
+/// DynamoDB items (and `AttributeValue::M`) expect.
+pub(crate) fn json_object_to_attributes(
+    map: Map<String, Value>,
+) -> HashMap<String, AttributeValue> {
+    map.into_iter()
+        .map(|(key, value)| (key, json_to_attribute(value)))
+        .collect()
+}
+
+/// Inverse of [`json_to_attribute`], used on the read path. `N` values are
+/// parsed back into JSON numbers, falling back to a string if they overflow the
+/// JSON number representation.
+pub(crate) fn attribute_to_json(value: AttributeValue) -> Value {
+    match value {
+        AttributeValue::Null(_) => Value::Null,
+        AttributeValue::Bool(b) => Value::Bool(b),
+        AttributeValue::N(num) => number_or_string(num),
+        AttributeValue::S(string) => Value::String(string),
+        AttributeValue::L(vec) => Value::Array(vec.into_iter().map(attribute_to_json).collect()),
+        AttributeValue::M(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (key, attribute_to_json(value)))
+                .collect(),
+        ),
+        // The remaining DynamoDB types (binary, sets, ...) are never produced by
+        // `json_to_attribute`, so they have no faithful JSON representation here.
+        other => Value::String(format!("{:?}", other)),
+    }
+}
+
+/// Parse a DynamoDB `N` value back into a JSON number, keeping the original
+/// string only when it cannot be represented losslessly. `serde_json` silently
+/// downgrades overflowing integers to a lossy `f64` rather than erroring, so we
+/// reject any parse that does not round-trip to the original text.
+fn number_or_string(num: String) -> Value {
+    match serde_json::from_str::<Value>(&num) {
+        Ok(value @ Value::Number(_)) if value.to_string() == num => value,
+        _ => Value::String(num),
     }
 }
 
@@ -93,10 +251,7 @@ fn write_request_from_json_object(
     value: Map<String, Value>,
 ) -> WriteRequest {
     // Build a hash map of AttributeValues
-    let mut map = value
-        .into_iter()
-        .map(|(key, value)| (key, json_to_attribute(value)))
-        .collect::<std::collections::HashMap<_, _>>();
+    let mut map = json_object_to_attributes(value);
     // Add the required key fields
     map.insert(
         "ProjectId".to_string(),
@@ -125,3 +280,41 @@ fn random_event() -> (Uuid, i64, Map<String, Value>) {
         .unwrap(),
     )
 }
+
+#[cfg(test)]
+mod test {
+    use super::{attribute_to_json, json_to_attribute};
+
+    use serde_json::json;
+
+    #[test]
+    fn round_trips_nested_value() {
+        let value = json!({
+            "EntityType": "task",
+            "Count": 42,
+            "Ratio": 0.5,
+            "Done": false,
+            "Missing": null,
+            "Tags": ["a", "b", 3],
+            "Nested": {
+                "Inner": { "Deep": [true, null, "leaf"] },
+                "Empty": {},
+            },
+        });
+
+        let attribute = json_to_attribute(value.clone());
+        let round_tripped = attribute_to_json(attribute);
+
+        assert_eq!(value, round_tripped);
+    }
+
+    #[test]
+    fn large_numbers_fall_back_to_string() {
+        // Beyond the integer range serde_json can represent exactly (it would
+        // parse as a lossy f64), so the read path keeps it as the original
+        // string rather than corrupting it.
+        let overflowing = "123456789012345678901234567890";
+        let attribute = aws_sdk_dynamodb::model::AttributeValue::N(overflowing.to_string());
+        assert_eq!(attribute_to_json(attribute), json!(overflowing));
+    }
+}
@@ -1,10 +1,18 @@
+use crate::checkpoint::CheckpointStore;
+use crate::configuration::OnDeserializeError;
+use crate::deadletter::{DeadLetter, DeadLetterStore};
 use crate::event::Event;
 
-use aws_sdk_kinesis::model::{Record, ShardIteratorType, StreamDescription};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use aws_sdk_kinesis::model::{Record, Shard, ShardIteratorType, StreamDescription};
 use aws_sdk_kinesis::output::GetRecordsOutput;
 use aws_sdk_kinesis::{Client, Config};
 use color_eyre::eyre::{eyre, Result, WrapErr};
 use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::Notify;
 
 #[derive(Clone)]
 pub struct KinesisWrapper {
@@ -46,6 +54,32 @@ impl KinesisWrapper {
             .ok_or_else(|| eyre!("No shard iterator"))
     }
 
+    /// The name of the stream this client is bound to.
+    pub fn stream_name(&self) -> &str {
+        &self.stream_name
+    }
+
+    /// Obtain a shard iterator positioned immediately after the given sequence
+    /// number, used to resume from a stored checkpoint.
+    pub async fn get_shard_iterator_after(
+        &self,
+        shard_id: &str,
+        sequence_number: &str,
+    ) -> Result<String> {
+        self.client
+            .get_shard_iterator()
+            .stream_name(&self.stream_name)
+            .shard_id(shard_id)
+            .shard_iterator_type(ShardIteratorType::AfterSequenceNumber)
+            .starting_sequence_number(sequence_number)
+            .send()
+            .await
+            .wrap_err("Failed to call `GetShardIterator`")?
+            .shard_iterator()
+            .map(|i| i.to_string())
+            .ok_or_else(|| eyre!("No shard iterator"))
+    }
+
     pub async fn get_records(&self, shard_iterator: &str) -> Result<GetRecordsOutput> {
         self.client
             .get_records()
@@ -54,31 +88,121 @@ impl KinesisWrapper {
             .await
             .wrap_err("Failed to call `GetRecords`")
     }
+
+    /// Put a single record onto the stream, using `partition_key` to route it to
+    /// a shard. Used by the benchmarking harness to drive synthetic load.
+    pub async fn put_record(&self, partition_key: &str, data: Vec<u8>) -> Result<()> {
+        self.client
+            .put_record()
+            .stream_name(&self.stream_name)
+            .partition_key(partition_key)
+            .data(aws_sdk_kinesis::types::Blob::new(data))
+            .send()
+            .await
+            .wrap_err("Failed to call `PutRecord`")?;
+        Ok(())
+    }
+
+    /// Enumerate every shard in the stream, paginating `DescribeStream` on
+    /// `ExclusiveStartShardId` until it reports no more shards.
+    pub async fn list_all_shards(&self) -> Result<Vec<Shard>> {
+        let mut shards = Vec::new();
+        let mut exclusive_start_shard_id: Option<String> = None;
+
+        loop {
+            let description = self
+                .client
+                .describe_stream()
+                .stream_name(&self.stream_name)
+                .set_exclusive_start_shard_id(exclusive_start_shard_id.clone())
+                .send()
+                .await
+                .wrap_err("Failed to call `DescribeStream`")?
+                .stream_description()
+                .cloned()
+                .ok_or_else(|| eyre!("No stream description"))?;
+
+            let page = description.shards().unwrap_or_default();
+            if let Some(last) = page.last() {
+                exclusive_start_shard_id = last.shard_id().map(|id| id.to_string());
+            }
+            shards.extend(page.iter().cloned());
+
+            if !description.has_more_shards() {
+                break;
+            }
+        }
+
+        Ok(shards)
+    }
 }
 
 pub struct KinesisShardReader {
     shard_id: String,
     client: KinesisWrapper,
     event_tx: UnboundedSender<Event>,
+    /// How long to sleep between `GetRecords` polls, to stay under the
+    /// 5 transactions/second/shard limit.
+    poll_interval: Duration,
+    /// Store used to resume from, and persist progress to. `None` disables
+    /// checkpointing, falling back to reading from `TrimHorizon`.
+    checkpoints: Option<CheckpointStore>,
+    /// What to do with records that fail to deserialize.
+    on_deserialize_error: OnDeserializeError,
+    /// Dead-letter store, used when `on_deserialize_error` is `DeadLetter`.
+    dead_letters: Option<DeadLetterStore>,
 }
 
 impl KinesisShardReader {
-    pub fn new(shard_id: String, client: KinesisWrapper, event_tx: UnboundedSender<Event>) -> Self {
+    pub fn new(
+        shard_id: String,
+        client: KinesisWrapper,
+        event_tx: UnboundedSender<Event>,
+        poll_interval: Duration,
+        checkpoints: Option<CheckpointStore>,
+        on_deserialize_error: OnDeserializeError,
+        dead_letters: Option<DeadLetterStore>,
+    ) -> Self {
         Self {
             shard_id,
             client,
             event_tx,
+            poll_interval,
+            checkpoints,
+            on_deserialize_error,
+            dead_letters,
         }
     }
 
     #[tracing::instrument(skip_all)]
     pub async fn run_until_shard_closed(self) -> Result<()> {
-        // Obtain an initial shard iterator
-        let mut shard_iterator = self
-            .client
-            .get_shard_iterator(&self.shard_id)
-            .await
-            .wrap_err("Failed to obtain shard iterator")?;
+        let stream_name = self.client.stream_name().to_string();
+
+        // Resume from the stored checkpoint if one exists, otherwise start from
+        // the trim horizon and replay the whole shard.
+        let checkpoint = match &self.checkpoints {
+            Some(store) => store
+                .load(&stream_name, &self.shard_id)
+                .await
+                .wrap_err("Failed to load checkpoint")?,
+            None => None,
+        };
+        let mut shard_iterator = match &checkpoint {
+            Some(sequence_number) => {
+                tracing::info!(%sequence_number, "Resuming shard from checkpoint");
+                self.client
+                    .get_shard_iterator_after(&self.shard_id, sequence_number)
+                    .await
+            }
+            None => self.client.get_shard_iterator(&self.shard_id).await,
+        }
+        .wrap_err("Failed to obtain shard iterator")?;
+
+        // Buffers checkpoint writes so we don't `PutItem` for every record.
+        let mut checkpointer = self
+            .checkpoints
+            .as_ref()
+            .map(|store| store.checkpointer(stream_name.clone(), self.shard_id.clone()));
 
         loop {
             // Call GetRecords
@@ -87,6 +211,21 @@ impl KinesisShardReader {
             // Handle each record received
             for record in resp.records().unwrap() {
                 self.handle_record(record).await?;
+
+                // CAUTION: this advances the checkpoint once the event has been
+                // enqueued onto the in-memory channel, NOT once the downstream
+                // `EventSink` has durably written it to DynamoDB. If the process
+                // dies after a checkpoint flush but before `receive_events`
+                // flushes that batch, those events are lost and will not be
+                // replayed (the stored sequence number has already moved past
+                // them) — an at-most-once window. Closing it requires the sink to
+                // ack sequence numbers back to the checkpointer; until then this
+                // is best-effort resume, not exactly-once delivery.
+                if let (Some(checkpointer), Some(sequence_number)) =
+                    (checkpointer.as_mut(), record.sequence_number())
+                {
+                    checkpointer.record(sequence_number).await?;
+                }
             }
 
             // Update the iterator
@@ -97,9 +236,13 @@ impl KinesisShardReader {
                 break;
             }
 
-            // Wait 200 ms (so as to not exceed the 5 transactions/second limit
-            // TODO: make this a config option
-            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            // Wait between polls so as to not exceed the 5 transactions/second limit
+            tokio::time::sleep(self.poll_interval).await;
+        }
+
+        // Persist any progress still buffered when the shard closes.
+        if let Some(mut checkpointer) = checkpointer {
+            checkpointer.flush().await?;
         }
 
         Ok(())
@@ -129,14 +272,198 @@ impl KinesisShardReader {
                     .send(event)
                     .wrap_err("Failed to send event over channel")?;
             }
-            // Don't pass the error up. All we have is a malformed message, report it and move on
+            // A malformed message. How we handle it depends on configuration.
             Err(err) => {
-                let raw_message = String::from_utf8_lossy(record.data().unwrap().as_ref());
-                // CLion may display an error on this line. You can ignore it, the code is correct.
+                let raw = record.data().unwrap().as_ref();
+                let raw_message = String::from_utf8_lossy(raw);
                 tracing::error!(%raw_message, "{}", err);
+
+                match self.on_deserialize_error {
+                    OnDeserializeError::Skip => {}
+                    OnDeserializeError::DeadLetter => {
+                        if let Some(store) = &self.dead_letters {
+                            let letter = DeadLetter {
+                                shard_id: &self.shard_id,
+                                sequence_number: record.sequence_number().unwrap_or_default(),
+                                partition_key: record.partition_key().unwrap_or_default(),
+                                raw,
+                                error: err.to_string(),
+                            };
+                            store
+                                .write(&letter)
+                                .await
+                                .wrap_err("Failed to dead-letter record")?;
+                        } else {
+                            tracing::warn!(
+                                "on_deserialize_error is `deadletter` but no dead-letter table is configured; dropping record"
+                            );
+                        }
+                    }
+                    OnDeserializeError::Fail => {
+                        return Err(err).wrap_err("Aborting shard reader on malformed record");
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Discovers every shard in a stream and drives one [`KinesisShardReader`] per
+/// shard through the shared `event_tx` channel.
+///
+/// To honour Kinesis' ordering guarantee across resharding, a child shard is
+/// not read until every one of its parent shards has been fully drained. The
+/// manager re-runs discovery periodically to pick up child shards created by
+/// splits and merges while it is running.
+pub struct ShardManager {
+    client: KinesisWrapper,
+    event_tx: UnboundedSender<Event>,
+    poll_interval: Duration,
+    rediscovery_interval: Duration,
+    checkpoints: Option<CheckpointStore>,
+    on_deserialize_error: OnDeserializeError,
+    dead_letters: Option<DeadLetterStore>,
+}
+
+impl ShardManager {
+    pub fn new(
+        client: KinesisWrapper,
+        event_tx: UnboundedSender<Event>,
+        poll_interval: Duration,
+        checkpoints: Option<CheckpointStore>,
+        on_deserialize_error: OnDeserializeError,
+        dead_letters: Option<DeadLetterStore>,
+    ) -> Self {
+        Self {
+            client,
+            event_tx,
+            poll_interval,
+            // Re-discover shards an order of magnitude less often than we poll;
+            // splits and merges are comparatively rare.
+            rediscovery_interval: poll_interval.saturating_mul(10),
+            checkpoints,
+            on_deserialize_error,
+            dead_letters,
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn run(self) -> Result<()> {
+        // Shards whose reader has fully drained them (`next_shard_iterator` was
+        // `None`), shared with every reader task.
+        let finished = Arc::new(Mutex::new(HashSet::<String>::new()));
+        // Woken whenever a parent shard finishes, so waiting children re-check.
+        let parents_done = Arc::new(Notify::new());
+
+        // Shards we have already spawned a reader for, so re-discovery only
+        // handles newly created shards.
+        let mut spawned = HashSet::<String>::new();
+        let mut handles = Vec::new();
+        // Readers report completion here so we can surface errors eagerly
+        // instead of gating both liveness and error reporting on the
+        // finished-set equality.
+        let (done_tx, mut done_rx) = tokio::sync::mpsc::unbounded_channel::<Result<()>>();
+        let mut completed = 0usize;
+
+        loop {
+            let shards = self
+                .client
+                .list_all_shards()
+                .await
+                .wrap_err("Failed to discover shards")?;
+            let known: HashSet<String> =
+                shards.iter().filter_map(|s| s.shard_id().map(str::to_string)).collect();
+
+            for shard in &shards {
+                let Some(shard_id) = shard.shard_id() else {
+                    continue;
+                };
+                if !spawned.insert(shard_id.to_string()) {
+                    continue;
+                }
+
+                // Only wait on parents we actually know about; older parents may
+                // already be trimmed away and will never be seen or finished.
+                let parents: Vec<String> = [shard.parent_shard_id(), shard.adjacent_parent_shard_id()]
+                    .into_iter()
+                    .flatten()
+                    .map(str::to_string)
+                    .filter(|p| known.contains(p))
+                    .collect();
+
+                let reader = KinesisShardReader::new(
+                    shard_id.to_string(),
+                    self.client.clone(),
+                    self.event_tx.clone(),
+                    self.poll_interval,
+                    self.checkpoints.clone(),
+                    self.on_deserialize_error,
+                    self.dead_letters.clone(),
+                );
+                let finished = Arc::clone(&finished);
+                let parents_done = Arc::clone(&parents_done);
+                let done_tx = done_tx.clone();
+                let shard_id = shard_id.to_string();
+
+                handles.push(tokio::task::spawn(async move {
+                    wait_for_parents(&parents, &finished, &parents_done).await;
+                    let result = reader.run_until_shard_closed().await;
+                    if result.is_ok() {
+                        finished.lock().unwrap().insert(shard_id);
+                        // Wake any child shards that may now be unblocked.
+                        parents_done.notify_waiters();
+                    }
+                    // Report completion; the receiver outlives every task.
+                    let _ = done_tx.send(result);
+                }));
+            }
+
+            // Every discovered shard has a reader and all of them have finished.
+            if completed == spawned.len() {
+                break;
+            }
+
+            // Wait for the next re-discovery tick, or surface a reader result as
+            // soon as it lands.
+            tokio::select! {
+                _ = tokio::time::sleep(self.rediscovery_interval) => {}
+                Some(result) = done_rx.recv() => {
+                    // A failing reader blocks its children forever, so abort the
+                    // whole fleet and propagate rather than looping.
+                    if let Err(err) = result {
+                        for handle in &handles {
+                            handle.abort();
+                        }
+                        return Err(err).wrap_err("Shard reader failed");
+                    }
+                    completed += 1;
+                }
             }
         }
 
         Ok(())
     }
 }
+
+/// Block until every parent shard has been recorded as finished, re-checking
+/// each time a shard completes.
+async fn wait_for_parents(
+    parents: &[String],
+    finished: &Mutex<HashSet<String>>,
+    parents_done: &Notify,
+) {
+    loop {
+        // Register for notification *before* checking, so we can't miss a
+        // completion that lands between the check and the await.
+        let notified = parents_done.notified();
+        if parents
+            .iter()
+            .all(|parent| finished.lock().unwrap().contains(parent))
+        {
+            return;
+        }
+        notified.await;
+    }
+}
@@ -1,15 +1,20 @@
+mod bench;
+mod checkpoint;
 mod configuration;
+mod deadletter;
 mod dynamo;
 mod event;
 mod kinesis;
 mod logging;
+mod sink;
 
 use configuration::{AwsConfig, BridgeServiceConfig};
 
 use std::path::{Path, PathBuf};
 
 use crate::event::Event;
-use crate::kinesis::KinesisShardReader;
+use crate::kinesis::ShardManager;
+use crate::sink::{EventSink, MAX_BATCH_SIZE};
 use color_eyre::eyre::{Result, WrapErr};
 use tokio::sync::mpsc::UnboundedReceiver;
 
@@ -21,9 +26,16 @@ async fn main() -> Result<()> {
     // Pretty error printing
     color_eyre::install()?;
 
-    let events_file_path = std::env::args()
-        .nth(1)
-        .unwrap_or_else(|| "events.json".to_string());
+    let mut args = std::env::args().skip(1).peekable();
+
+    // `bench <workload.json>...` runs the load-testing harness instead of the
+    // bridge itself.
+    if args.peek().map(String::as_str) == Some("bench") {
+        args.next();
+        return run_bench(args.map(PathBuf::from).collect()).await;
+    }
+
+    let events_file_path = args.next().unwrap_or_else(|| "events.json".to_string());
     let events_file_path = PathBuf::from(events_file_path);
     let _events = load_events_file(&events_file_path).wrap_err("Failed to load events file")?;
 
@@ -34,9 +46,32 @@ async fn main() -> Result<()> {
         .wrap_err("Failed to load AWS config")?;
 
     // Initialize dynamo and kinesis clients
-    let dynamo_client = dynamo::DynamoWrapper::new(config.dynamo_table_name, aws_config.dynamo);
+    let dynamo_client = dynamo::DynamoWrapper::new(
+        config.dynamo_table_name,
+        aws_config.dynamo.clone(),
+        config.max_write_attempts,
+    );
     tracing::info!("DynamoDB Client initialized");
 
+    // Checkpoint store so shard readers resume from their last sequence number
+    let checkpoints = checkpoint::CheckpointStore::new(
+        config.checkpoint_table_name,
+        aws_config.dynamo.clone(),
+        checkpoint::FlushPolicy {
+            every_records: config.checkpoint_flush_records,
+            every_interval: std::time::Duration::from_millis(config.checkpoint_flush_interval_ms),
+        },
+    );
+    tracing::info!("Checkpoint store initialized");
+
+    // Dead-letter store for records that fail to deserialize, if configured
+    let dead_letters = config
+        .dead_letter_table_name
+        .map(|table| deadletter::DeadLetterStore::new(table, aws_config.dynamo));
+    if dead_letters.is_some() {
+        tracing::info!("Dead-letter store initialized");
+    }
+
     let kinesis_client =
         kinesis::KinesisWrapper::new(config.kinesis_stream_name, aws_config.kinesis);
     tracing::info!("Kinesis Client initialized");
@@ -45,44 +80,94 @@ async fn main() -> Result<()> {
     dynamo_client.list_tables().await?;
     dynamo_client.send_batch_write().await?;
 
-    // Get metadata on kinesis stream
-    let stream = kinesis_client
-        .describe_stream()
-        .await
-        .wrap_err("Failed to describe stream")?;
-    let first_shard_id = stream.shards().expect("at least one shard")[0]
-        .shard_id()
-        .expect("shard id");
-
     // Create a channel over which kinesis shard readers can send events
     let (event_tx, event_rx) = tokio::sync::mpsc::unbounded_channel();
-    let shard_reader = KinesisShardReader::new(
-        first_shard_id.to_string(),
-        // The shard reader gets a copy of the `Sender`
+
+    // The shard manager discovers every shard in the stream and drives one
+    // reader per shard, respecting parent/child ordering across resharding.
+    let shard_manager = ShardManager::new(
+        // The manager gets a copy of the `Sender` to hand to each reader
         kinesis_client.clone(),
         event_tx.clone(),
+        std::time::Duration::from_millis(config.poll_interval_ms),
+        Some(checkpoints),
+        config.on_deserialize_error,
+        dead_letters,
     );
+    // Dropping the original sender lets `receive_events` terminate once every
+    // reader (and their clones) have closed.
+    drop(event_tx);
 
-    // Start the shard reader in an asynchronous task
-    let shard_reader_handle = tokio::task::spawn(shard_reader.run_until_shard_closed());
+    // Start the shard manager in an asynchronous task
+    let shard_manager_handle = tokio::task::spawn(shard_manager.run());
 
-    // Start another task for the receiver of the events (eventually this would be something that
-    // writes events to DynamoDB or processes them for webhooks
-    let shard_collator_thread = tokio::task::spawn(receive_events(event_rx));
+    // Start another task for the receiver of the events, buffering them into
+    // batches and flushing them through the DynamoDB sink.
+    let shard_collator_thread = tokio::task::spawn(receive_events(event_rx, dynamo_client));
 
     // Wait for the tasks to complete
-    shard_reader_handle
+    shard_manager_handle
         .await
         .unwrap()
-        .wrap_err("Error in shard reader")?;
-    shard_collator_thread.await.unwrap();
+        .wrap_err("Error in shard manager")?;
+    shard_collator_thread.await.unwrap()?;
     Ok(())
 }
 
-async fn receive_events(mut event_rx: UnboundedReceiver<Event>) {
+/// Drain events off the channel, buffering them into batches of up to
+/// [`MAX_BATCH_SIZE`] and flushing each through the sink.
+async fn receive_events(mut event_rx: UnboundedReceiver<Event>, sink: impl EventSink) -> Result<()> {
+    let mut batch = Vec::with_capacity(MAX_BATCH_SIZE);
+
     while let Some(event) = event_rx.recv().await {
-        dbg!(&event);
+        batch.push(event);
+        if batch.len() >= MAX_BATCH_SIZE {
+            sink.write(&batch).await.wrap_err("Failed to flush batch")?;
+            batch.clear();
+        }
     }
+
+    // Flush whatever is left once the channel closes.
+    if !batch.is_empty() {
+        sink.write(&batch)
+            .await
+            .wrap_err("Failed to flush final batch")?;
+    }
+
+    Ok(())
+}
+
+/// Run the load-testing harness over one or more workload files. The report
+/// folder and optional baseline report are taken from the environment.
+async fn run_bench(workload_paths: Vec<PathBuf>) -> Result<()> {
+    if workload_paths.is_empty() {
+        return Err(color_eyre::eyre::eyre!(
+            "Usage: bench <workload.json> [workload.json ...]"
+        ));
+    }
+
+    let config = BridgeServiceConfig::load().wrap_err("Failed to load app config")?;
+    let aws_config = AwsConfig::load(config.override_aws_endpoint)
+        .await
+        .wrap_err("Failed to load AWS config")?;
+    let kinesis_config = aws_sdk_kinesis::config::Builder::from(&aws_config.aws).build();
+
+    let report_dir =
+        PathBuf::from(std::env::var("BENCH_REPORT_DIR").unwrap_or_else(|_| "bench_reports".into()));
+    let compare_to = std::env::var("BENCH_COMPARE_TO").ok().map(PathBuf::from);
+    let threshold = std::env::var("BENCH_REGRESSION_THRESHOLD")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(0.2);
+
+    bench::run(
+        kinesis_config,
+        &workload_paths,
+        &report_dir,
+        compare_to.as_deref(),
+        threshold,
+    )
+    .await
 }
 
 pub fn load_events_file(path: &Path) -> Result<Vec<serde_json::Value>> {
@@ -0,0 +1,14 @@
+use crate::event::Event;
+
+use async_trait::async_trait;
+use color_eyre::eyre::Result;
+
+/// DynamoDB's `BatchWriteItem` accepts at most 25 requests per call.
+pub const MAX_BATCH_SIZE: usize = 25;
+
+/// A destination for parsed [`Event`]s. Implementors are responsible for
+/// durably persisting a batch, including any provider-specific retry logic.
+#[async_trait]
+pub trait EventSink {
+    async fn write(&self, events: &[Event]) -> Result<()>;
+}